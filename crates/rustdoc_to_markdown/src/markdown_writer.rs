@@ -1,5 +1,5 @@
 use std::cell::RefCell;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::sync::OnceLock;
 
 use anyhow::Result;
@@ -18,18 +18,551 @@ fn more_than_three_newlines_regex() -> &'static Regex {
 }
 
 #[derive(Debug, Clone)]
-struct HtmlElement {
-    tag: String,
+pub struct HtmlElement {
+    pub tag: String,
     attrs: RefCell<Vec<Attribute>>,
 }
 
-enum StartTagOutcome {
+impl HtmlElement {
+    pub fn attr(&self, name: &str) -> Option<String> {
+        self.attrs
+            .borrow()
+            .iter()
+            .find(|attr| attr.name.local.to_string() == name)
+            .map(|attr| attr.value.to_string())
+    }
+
+    fn has_class(&self, class: &str) -> bool {
+        self.attr("class")
+            .is_some_and(|classes| classes.split(' ').any(|c| c.trim() == class))
+    }
+}
+
+pub enum StartTagOutcome {
     Continue,
     Skip,
 }
 
+/// An element handler that `MarkdownWriter` consults for every tag it
+/// visits. Handlers are tried in registration order; the first one whose
+/// `should_handle` returns `true` for a tag owns both its `start` and `end`.
+///
+/// This is the extension point for teaching `MarkdownWriter` about HTML
+/// that isn't rustdoc-specific: implement this trait and register an
+/// instance with [`MarkdownWriter::push_handler`] instead of editing the
+/// built-in handlers.
+pub trait TagHandler {
+    fn should_handle(&self, tag: &str) -> bool;
+
+    fn start(&self, tag: &HtmlElement, writer: &mut MarkdownWriter) -> StartTagOutcome;
+
+    fn end(&self, _tag: &HtmlElement, _writer: &mut MarkdownWriter) {}
+}
+
+/// Generates GitHub-style heading slugs, disambiguating repeats with a
+/// `-1`, `-2`, ... suffix, the same way rustdoc's `IdMap` does.
+#[derive(Debug, Default)]
+struct IdMap {
+    counts: HashMap<String, usize>,
+}
+
+impl IdMap {
+    /// Returns a unique slug for `text`, remembering it so later calls with
+    /// the same text get disambiguated.
+    fn slug(&mut self, text: &str) -> String {
+        let base = Self::sluggify(text);
+        let count = self.counts.entry(base.clone()).or_insert(0);
+        let slug = if *count == 0 {
+            base
+        } else {
+            format!("{base}-{count}")
+        };
+        *count += 1;
+
+        slug
+    }
+
+    fn sluggify(text: &str) -> String {
+        let mut slug = String::new();
+        let mut last_was_dash = false;
+
+        for ch in text.trim().chars() {
+            if ch.is_ascii_alphanumeric() {
+                slug.push(ch.to_ascii_lowercase());
+                last_was_dash = false;
+            } else if !last_was_dash {
+                slug.push('-');
+                last_was_dash = true;
+            }
+        }
+
+        slug.trim_matches('-').to_string()
+    }
+}
+
+/// One heading recorded when [`MarkdownWriter::with_heading_anchors`] is
+/// enabled, in document order.
+#[derive(Debug, Clone)]
+pub struct HeadingAnchor {
+    pub level: u8,
+    pub text: String,
+    pub slug: String,
+}
+
+/// Handles `<h1>`-`<h6>`.
+struct HeadingHandler;
+
+impl TagHandler for HeadingHandler {
+    fn should_handle(&self, tag: &str) -> bool {
+        matches!(tag, "h1" | "h2" | "h3" | "h4" | "h5" | "h6")
+    }
+
+    fn start(&self, tag: &HtmlElement, writer: &mut MarkdownWriter) -> StartTagOutcome {
+        match tag.tag.as_str() {
+            "h1" => writer.push_str("\n\n# "),
+            "h2" => writer.push_str("\n\n## "),
+            "h3" => writer.push_str("\n\n### "),
+            "h4" => writer.push_str("\n\n#### "),
+            "h5" => writer.push_str("\n\n##### "),
+            "h6" => writer.push_str("\n\n###### "),
+            _ => {}
+        }
+
+        if writer.heading_ids.is_some() {
+            let level = tag.tag[1..].parse().unwrap_or(1);
+            writer.heading_starts.push((writer.markdown.len(), level));
+            writer.heading_text_stack.push(String::new());
+        }
+
+        StartTagOutcome::Continue
+    }
+
+    fn end(&self, _tag: &HtmlElement, writer: &mut MarkdownWriter) {
+        if let Some((_start, level)) = writer.heading_starts.pop() {
+            let text = writer
+                .heading_text_stack
+                .pop()
+                .unwrap_or_default()
+                .trim()
+                .to_string();
+            if let Some(heading_ids) = writer.heading_ids.as_mut() {
+                let slug = heading_ids.slug(&text);
+
+                if writer.emit_heading_anchors {
+                    writer.push_str_closing(&format!(" {{#{slug}}}"));
+                }
+
+                writer.headings.push(HeadingAnchor { level, text, slug });
+            }
+        }
+
+        writer.push_str_closing("\n\n");
+    }
+}
+
+/// Handles `<code>` and `<pre>`, deriving the fence info string for code
+/// blocks from the conventional `language-xxx`/`lang-xxx`/
+/// `highlight-source-xxx` class prefixes (as well as bare language names),
+/// the same info rustdoc's `CodeBlockKind` carries.
+struct CodeHandler;
+
+impl CodeHandler {
+    /// Maps a detected language token onto the fence info string rustdoc
+    /// would use (falling back to the token itself).
+    fn alias(language: &str) -> String {
+        match language {
+            "rust" => "rs".to_string(),
+            other => other.to_string(),
+        }
+    }
+
+    const KNOWN_LANGUAGES: &'static [&'static str] = &[
+        "rust", "rs", "python", "py", "javascript", "js", "typescript", "ts", "go", "ruby", "c",
+        "cpp", "bash", "sh", "json", "toml", "yaml", "html", "css",
+    ];
+
+    /// Scans `tag`'s class list for a fenced-code language, preferring an
+    /// explicit `language-`/`lang-`/`highlight-source-` prefix over a bare
+    /// known language name. Returns an empty string when nothing matches.
+    fn detect_language(tag: &HtmlElement) -> String {
+        let classes = tag
+            .attr("class")
+            .map(|classes| {
+                classes
+                    .split(' ')
+                    .map(|class| class.trim().to_string())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        for class in &classes {
+            if let Some(language) = class
+                .strip_prefix("language-")
+                .or_else(|| class.strip_prefix("lang-"))
+                .or_else(|| class.strip_prefix("highlight-source-"))
+            {
+                return Self::alias(language);
+            }
+        }
+
+        for class in &classes {
+            if Self::KNOWN_LANGUAGES.contains(&class.as_str()) {
+                return Self::alias(class);
+            }
+        }
+
+        String::new()
+    }
+}
+
+impl TagHandler for CodeHandler {
+    fn should_handle(&self, tag: &str) -> bool {
+        matches!(tag, "code" | "pre")
+    }
+
+    fn start(&self, tag: &HtmlElement, writer: &mut MarkdownWriter) -> StartTagOutcome {
+        match tag.tag.as_str() {
+            "code" => {
+                if !writer.is_inside("pre") {
+                    writer.push_str("`")
+                } else if let Some(slot) = writer.fence_language_slot.take() {
+                    // The `<pre>` itself had no language class; rustdoc and
+                    // most doc generators put it on the nested `<code>`
+                    // instead, so patch the fence we already opened.
+                    let language = Self::detect_language(tag);
+                    if !language.is_empty() {
+                        writer.markdown.insert_str(slot, &language);
+                    }
+                }
+            }
+            "pre" => {
+                let language = Self::detect_language(tag);
+                writer.push_str("\n```");
+                if language.is_empty() {
+                    writer.fence_language_slot = Some(writer.markdown.len());
+                } else {
+                    writer.push_str(&language);
+                }
+                writer.push_str("\n");
+            }
+            _ => {}
+        }
+
+        StartTagOutcome::Continue
+    }
+
+    fn end(&self, tag: &HtmlElement, writer: &mut MarkdownWriter) {
+        match tag.tag.as_str() {
+            "code" => {
+                if !writer.is_inside("pre") {
+                    writer.push_str_closing("`")
+                }
+            }
+            "pre" => {
+                writer.fence_language_slot = None;
+                writer.push_str_closing("\n```\n");
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Handles `<em>`/`<i>`, `<strong>`/`<b>`, and `<del>`/`<s>`.
+struct InlineHandler;
+
+impl InlineHandler {
+    fn marker(tag: &str) -> &'static str {
+        match tag {
+            "em" | "i" => "*",
+            "strong" | "b" => "**",
+            "del" | "s" => "~~",
+            _ => "",
+        }
+    }
+}
+
+impl TagHandler for InlineHandler {
+    fn should_handle(&self, tag: &str) -> bool {
+        matches!(tag, "em" | "i" | "strong" | "b" | "del" | "s")
+    }
+
+    fn start(&self, tag: &HtmlElement, writer: &mut MarkdownWriter) -> StartTagOutcome {
+        if !writer.is_inside("pre") {
+            writer.push_str(Self::marker(&tag.tag));
+        }
+
+        StartTagOutcome::Continue
+    }
+
+    fn end(&self, tag: &HtmlElement, writer: &mut MarkdownWriter) {
+        if !writer.is_inside("pre") {
+            writer.push_str_closing(Self::marker(&tag.tag));
+        }
+    }
+}
+
+/// Tracks one level of `<ul>`/`<ol>` nesting so `<li>` can indent by depth
+/// and ordered lists can number their items.
+struct ListFrame {
+    ordered: bool,
+    counter: usize,
+}
+
+/// Handles `<ul>`/`<ol>`/`<li>` (indentation and numbering) plus task-list
+/// `<input type="checkbox">` items.
+struct ListHandler;
+
+impl TagHandler for ListHandler {
+    fn should_handle(&self, tag: &str) -> bool {
+        matches!(tag, "ul" | "ol" | "li" | "input")
+    }
+
+    fn start(&self, tag: &HtmlElement, writer: &mut MarkdownWriter) -> StartTagOutcome {
+        match tag.tag.as_str() {
+            "ul" | "ol" => {
+                writer.push_newline();
+                let ordered = tag.tag == "ol";
+                let start = tag
+                    .attr("start")
+                    .and_then(|start| start.parse::<usize>().ok())
+                    .unwrap_or(1);
+                writer.list_stack.push(ListFrame {
+                    ordered,
+                    counter: start,
+                });
+            }
+            "li" => {
+                let depth = writer.list_stack.len().saturating_sub(1);
+                writer.push_str(&"  ".repeat(depth));
+
+                let ordered_counter = writer
+                    .list_stack
+                    .last()
+                    .filter(|list| list.ordered)
+                    .map(|list| list.counter);
+
+                match ordered_counter {
+                    Some(counter) => {
+                        writer.push_str(&format!("{counter}. "));
+                        if let Some(list) = writer.list_stack.last_mut() {
+                            list.counter += 1;
+                        }
+                    }
+                    None => writer.push_str("- "),
+                }
+            }
+            "input" => {
+                if writer.is_inside("li") && tag.attr("type").as_deref() == Some("checkbox") {
+                    let checked = tag.attr("checked").is_some();
+                    writer.push_str(if checked { "[x] " } else { "[ ] " });
+                }
+            }
+            _ => {}
+        }
+
+        StartTagOutcome::Continue
+    }
+
+    fn end(&self, tag: &HtmlElement, writer: &mut MarkdownWriter) {
+        match tag.tag.as_str() {
+            "ul" | "ol" => {
+                writer.list_stack.pop();
+                writer.push_newline();
+            }
+            "li" => writer.push_newline(),
+            _ => {}
+        }
+    }
+}
+
+/// Accumulates rows for a `<table>` while its children are visited, so the
+/// whole table can be emitted as GFM once its structure is known.
+#[derive(Debug, Default)]
+struct TableState {
+    header: Option<Vec<String>>,
+    rows: Vec<Vec<String>>,
+    current_row: Vec<String>,
+}
+
+/// Handles `<table>`/`<tr>`/`<th>`/`<td>`, buffering rows and emitting GFM
+/// once the table closes.
+struct TableHandler;
+
+impl TagHandler for TableHandler {
+    fn should_handle(&self, tag: &str) -> bool {
+        matches!(tag, "table" | "tr" | "th" | "td")
+    }
+
+    fn start(&self, tag: &HtmlElement, writer: &mut MarkdownWriter) -> StartTagOutcome {
+        match tag.tag.as_str() {
+            "table" => writer.table_state_stack.push(TableState::default()),
+            "tr" => {
+                if let Some(table) = writer.table_state_stack.last_mut() {
+                    table.current_row.clear();
+                }
+            }
+            "th" | "td" => writer.table_cell_starts.push(writer.markdown.len()),
+            _ => {}
+        }
+
+        StartTagOutcome::Continue
+    }
+
+    fn end(&self, tag: &HtmlElement, writer: &mut MarkdownWriter) {
+        match tag.tag.as_str() {
+            "th" | "td" => {
+                if let Some(start) = writer.table_cell_starts.pop() {
+                    let cell_text = writer.markdown.split_off(start);
+                    if let Some(table) = writer.table_state_stack.last_mut() {
+                        table
+                            .current_row
+                            .push(MarkdownWriter::escape_table_cell(cell_text.trim()));
+                    }
+                }
+            }
+            "tr" => {
+                let inside_thead = writer.is_inside("thead");
+                if let Some(table) = writer.table_state_stack.last_mut() {
+                    // Tables with a `<thead>` use its row as the header.
+                    // Raw HTML tables with no `<thead>` (the common
+                    // hand-written case) have no other way to mark a
+                    // header row, so promote whichever `<tr>` comes first.
+                    let is_header =
+                        inside_thead || (table.header.is_none() && table.rows.is_empty());
+                    let row = std::mem::take(&mut table.current_row);
+                    if is_header {
+                        table.header = Some(row);
+                    } else {
+                        table.rows.push(row);
+                    }
+                }
+            }
+            "table" => {
+                if let Some(table) = writer.table_state_stack.pop() {
+                    writer.render_table(table);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Handles `<a>` and `<img>`, rewriting `href`/`src` through the writer's
+/// link-replacement table.
+struct LinkHandler;
+
+impl TagHandler for LinkHandler {
+    fn should_handle(&self, tag: &str) -> bool {
+        matches!(tag, "a" | "img")
+    }
+
+    fn start(&self, tag: &HtmlElement, writer: &mut MarkdownWriter) -> StartTagOutcome {
+        match tag.tag.as_str() {
+            "a" => {
+                let href = tag.attr("href").map(|href| writer.resolve_url(&href));
+                writer.anchor_starts.push((writer.markdown.len(), href));
+            }
+            "img" => {
+                let src = tag
+                    .attr("src")
+                    .map(|src| writer.resolve_url(&src))
+                    .unwrap_or_default();
+                let alt = tag.attr("alt").unwrap_or_default();
+                writer.push_str(&format!("![{alt}]({src})"));
+            }
+            _ => {}
+        }
+
+        StartTagOutcome::Continue
+    }
+
+    fn end(&self, tag: &HtmlElement, writer: &mut MarkdownWriter) {
+        if tag.tag == "a" {
+            if let Some((start, Some(href))) = writer.anchor_starts.pop() {
+                let text = writer.markdown.split_off(start);
+                writer.push_str_closing(&format!("[{text}]({href})"));
+            }
+        }
+    }
+}
+
+/// Handles the rustdoc-page-specific skipping and formatting that isn't
+/// meaningful outside of generated rustdoc HTML: navigation chrome, the
+/// "Show declaration"/`hideme` summaries, and the `item-name` code styling.
+struct RustdocHandler;
+
+impl TagHandler for RustdocHandler {
+    fn should_handle(&self, tag: &str) -> bool {
+        matches!(tag, "head" | "script" | "nav" | "summary" | "div" | "span")
+    }
+
+    fn start(&self, tag: &HtmlElement, writer: &mut MarkdownWriter) -> StartTagOutcome {
+        match tag.tag.as_str() {
+            "head" | "script" | "nav" => return StartTagOutcome::Skip,
+            "summary" if tag.has_class("hideme") => return StartTagOutcome::Skip,
+            "div" | "span" => {
+                let classes_to_skip = ["nav-container", "sidebar-elems", "out-of-band"];
+                if classes_to_skip.iter().any(|class| tag.has_class(class)) {
+                    return StartTagOutcome::Skip;
+                }
+
+                if tag.has_class("item-name") {
+                    writer.push_str("`");
+                }
+            }
+            _ => {}
+        }
+
+        StartTagOutcome::Continue
+    }
+
+    fn end(&self, tag: &HtmlElement, writer: &mut MarkdownWriter) {
+        if tag.tag == "div" && tag.has_class("item-name") {
+            writer.push_str_closing("`: ");
+        }
+    }
+}
+
 pub struct MarkdownWriter {
     current_element_stack: VecDeque<HtmlElement>,
+    handlers: Vec<Box<dyn TagHandler>>,
+    table_state_stack: Vec<TableState>,
+    table_cell_starts: Vec<usize>,
+    list_stack: Vec<ListFrame>,
+    /// Pending `<a>` tags: the offset into `markdown` where their link text
+    /// starts, and the resolved `href` (`None` when the anchor has no href).
+    anchor_starts: Vec<(usize, Option<String>)>,
+    /// Base URL that relative `href`/`src` values are resolved against.
+    base_url: Option<String>,
+    /// `(from, to)` pairs used to rewrite `href`/`src` values, e.g. to turn
+    /// rustdoc's relative cross-references into absolute links.
+    link_replacements: Vec<(String, String)>,
+    /// `Some` once [`Self::with_heading_anchors`] opts into heading slugs.
+    heading_ids: Option<IdMap>,
+    /// Whether to emit a `{#slug}` attribute after each heading's text.
+    emit_heading_anchors: bool,
+    /// Pending headings: the offset into `markdown` where their text
+    /// starts, and their level.
+    heading_starts: Vec<(usize, u8)>,
+    /// Plain text accumulated for each pending heading in `heading_starts`,
+    /// independent of `markdown` so nested handlers (e.g. `LinkHandler`
+    /// splicing in `[text](href)`) can't corrupt the recorded heading text
+    /// or slug.
+    heading_text_stack: Vec<String>,
+    /// Headings recorded in document order, once heading anchors are enabled.
+    headings: Vec<HeadingAnchor>,
+    /// Offset into `markdown` where a `<pre>`'s fence info string goes,
+    /// left pending when the `<pre>` itself had no detectable language so
+    /// a nested `<code>`'s class can fill it in.
+    fence_language_slot: Option<usize>,
+    /// Character budget for [`Self::run_truncated`]; `None` means unlimited.
+    max_length: Option<usize>,
+    /// Visible (non-`<pre>`) characters written so far, counted against
+    /// `max_length`.
+    visible_len: usize,
+    /// Set once `visible_len` has exceeded `max_length`; once `true`,
+    /// traversal stops descending into further nodes.
+    truncated: bool,
     /// The Markdown output.
     markdown: String,
 }
@@ -38,18 +571,142 @@ impl MarkdownWriter {
     pub fn new() -> Self {
         Self {
             current_element_stack: VecDeque::new(),
+            handlers: Self::default_handlers(),
+            table_state_stack: Vec::new(),
+            table_cell_starts: Vec::new(),
+            list_stack: Vec::new(),
+            anchor_starts: Vec::new(),
+            base_url: None,
+            link_replacements: Vec::new(),
+            heading_ids: None,
+            emit_heading_anchors: false,
+            heading_starts: Vec::new(),
+            heading_text_stack: Vec::new(),
+            headings: Vec::new(),
+            fence_language_slot: None,
+            max_length: None,
+            visible_len: 0,
+            truncated: false,
             markdown: String::new(),
         }
     }
 
+    /// Opts into recording an ordered `(level, text, slug)` list of the
+    /// document's headings, retrievable via [`Self::headings`] after
+    /// [`Self::run`]. When `emit_anchors` is `true`, each heading also gets
+    /// a trailing `{#slug}` attribute in the Markdown output.
+    pub fn with_heading_anchors(mut self, emit_anchors: bool) -> Self {
+        self.heading_ids = Some(IdMap::default());
+        self.emit_heading_anchors = emit_anchors;
+        self
+    }
+
+    /// The headings encountered by the last [`Self::run`], in document
+    /// order. Empty unless [`Self::with_heading_anchors`] was used.
+    pub fn headings(&self) -> &[HeadingAnchor] {
+        &self.headings
+    }
+
+    /// The handlers `MarkdownWriter` registers by default: general Markdown
+    /// conversion plus rustdoc-page-specific skipping. Use [`Self::with_handlers`]
+    /// to compose a different set for non-rustdoc HTML.
+    fn default_handlers() -> Vec<Box<dyn TagHandler>> {
+        vec![
+            Box::new(HeadingHandler),
+            Box::new(CodeHandler),
+            Box::new(InlineHandler),
+            Box::new(ListHandler),
+            Box::new(TableHandler),
+            Box::new(LinkHandler),
+            Box::new(RustdocHandler),
+        ]
+    }
+
+    /// Replaces the writer's handler set entirely, e.g. to drop
+    /// `RustdocHandler` when converting non-rustdoc HTML.
+    pub fn with_handlers(mut self, handlers: Vec<Box<dyn TagHandler>>) -> Self {
+        self.handlers = handlers;
+        self
+    }
+
+    /// Registers an additional handler, tried after the existing ones.
+    pub fn push_handler(mut self, handler: Box<dyn TagHandler>) -> Self {
+        self.handlers.push(handler);
+        self
+    }
+
+    pub fn with_link_replacements(
+        mut self,
+        base_url: impl Into<String>,
+        link_replacements: Vec<(String, String)>,
+    ) -> Self {
+        self.base_url = Some(base_url.into());
+        self.link_replacements = link_replacements;
+        self
+    }
+
     fn is_inside(&self, tag: &str) -> bool {
         self.current_element_stack
             .iter()
             .any(|parent_element| parent_element.tag == tag)
     }
 
-    /// Appends the given string slice onto the end of the Markdown output.
+    /// Rewrites `url` through `link_replacements`, then resolves it against
+    /// `base_url` if it's still relative.
+    fn resolve_url(&self, url: &str) -> String {
+        let rewritten = self
+            .link_replacements
+            .iter()
+            .find(|(from, _)| from == url)
+            .map(|(_, to)| to.clone())
+            .unwrap_or_else(|| url.to_string());
+
+        if rewritten.starts_with("http://")
+            || rewritten.starts_with("https://")
+            || rewritten.starts_with('#')
+        {
+            return rewritten;
+        }
+
+        match &self.base_url {
+            Some(base_url) => format!(
+                "{}/{}",
+                base_url.trim_end_matches('/'),
+                rewritten.trim_start_matches('/')
+            ),
+            None => rewritten,
+        }
+    }
+
+    /// Appends the given string slice onto the end of the Markdown output,
+    /// counting it against `max_length` unless it's inside a `<pre>`.
     fn push_str(&mut self, str: &str) {
+        if self.truncated {
+            return;
+        }
+
+        self.markdown.push_str(str);
+
+        if self.is_inside("pre") {
+            return;
+        }
+
+        if let Some(max_length) = self.max_length {
+            self.visible_len += str.chars().count();
+            if self.visible_len > max_length {
+                self.truncated = true;
+                self.markdown.push('…');
+            }
+        }
+    }
+
+    /// Like [`Self::push_str`], but still writes after truncation. Reserved
+    /// for the bounded set of structural closers — closing emphasis/code
+    /// markers, and the `<a>`/table-cell/heading patterns that re-flow text
+    /// already written (and already counted against `max_length`) into its
+    /// final form — so a mid-element truncation still unwinds to valid,
+    /// closed Markdown instead of silently dropping content.
+    fn push_str_closing(&mut self, str: &str) {
         self.markdown.push_str(str);
     }
 
@@ -58,9 +715,26 @@ impl MarkdownWriter {
         self.push_str("\n");
     }
 
-    pub fn run(mut self, root_node: &Handle) -> Result<String> {
-        self.visit_node(&root_node)?;
-        Ok(Self::prettify_markdown(self.markdown))
+    pub fn run(&mut self, root_node: &Handle) -> Result<String> {
+        self.headings.clear();
+        if self.heading_ids.is_some() {
+            self.heading_ids = Some(IdMap::default());
+        }
+        self.visible_len = 0;
+        self.truncated = false;
+        self.visit_node(root_node)?;
+        Ok(Self::prettify_markdown(std::mem::take(&mut self.markdown)))
+    }
+
+    /// Like [`Self::run`], but stops once `max_length` visible characters
+    /// have been written, closing any tags still open (e.g. an unclosed
+    /// fence or emphasis marker) so the result stays well-formed Markdown.
+    /// Intended for short excerpts such as hover tooltips or search previews.
+    pub fn run_truncated(&mut self, root_node: &Handle, max_length: usize) -> Result<String> {
+        self.max_length = Some(max_length);
+        let markdown = self.run(root_node);
+        self.max_length = None;
+        markdown
     }
 
     fn prettify_markdown(markdown: String) -> String {
@@ -111,6 +785,9 @@ impl MarkdownWriter {
         }
 
         for child in node.children.borrow().iter() {
+            if self.truncated {
+                break;
+            }
             self.visit_node(child)?;
         }
 
@@ -122,91 +799,79 @@ impl MarkdownWriter {
         Ok(())
     }
 
+    /// Dispatches to the first registered handler that claims `tag`,
+    /// falling back to doing nothing for unrecognized tags.
     fn start_tag(&mut self, tag: &HtmlElement) -> StartTagOutcome {
-        match tag.tag.as_str() {
-            "head" | "script" | "nav" => return StartTagOutcome::Skip,
-            "h1" => self.push_str("\n\n# "),
-            "h2" => self.push_str("\n\n## "),
-            "h3" => self.push_str("\n\n### "),
-            "h4" => self.push_str("\n\n#### "),
-            "h5" => self.push_str("\n\n##### "),
-            "h6" => self.push_str("\n\n###### "),
-            "code" => {
-                if !self.is_inside("pre") {
-                    self.push_str("`")
-                }
-            }
-            "pre" => {
-                let attrs = tag.attrs.borrow();
-                let classes = attrs
-                    .iter()
-                    .find(|attr| attr.name.local.to_string() == "class")
-                    .map(|attr| {
-                        attr.value
-                            .split(' ')
-                            .map(|class| class.trim())
-                            .collect::<Vec<_>>()
-                    })
-                    .unwrap_or_default();
-                let is_rust = classes.into_iter().any(|class| class == "rust");
-                let language = if is_rust { "rs" } else { "" };
+        let handlers = std::mem::take(&mut self.handlers);
 
-                self.push_str(&format!("\n```{language}\n"))
+        let mut outcome = StartTagOutcome::Continue;
+        for handler in &handlers {
+            if handler.should_handle(&tag.tag) {
+                outcome = handler.start(tag, self);
+                break;
             }
-            "ul" | "ol" => self.push_newline(),
-            "li" => self.push_str("- "),
-            "summary" => {
-                if tag.attrs.borrow().iter().any(|attr| {
-                    attr.name.local.to_string() == "class" && attr.value.to_string() == "hideme"
-                }) {
-                    return StartTagOutcome::Skip;
-                }
-            }
-            "div" | "span" => {
-                let classes_to_skip = ["nav-container", "sidebar-elems", "out-of-band"];
+        }
 
-                if tag.attrs.borrow().iter().any(|attr| {
-                    attr.name.local.to_string() == "class"
-                        && attr
-                            .value
-                            .split(' ')
-                            .any(|class| classes_to_skip.contains(&class.trim()))
-                }) {
-                    return StartTagOutcome::Skip;
-                }
+        self.handlers = handlers;
+        outcome
+    }
 
-                if tag.attrs.borrow().iter().any(|attr| {
-                    attr.name.local.to_string() == "class" && attr.value.to_string() == "item-name"
-                }) {
-                    self.push_str("`");
-                }
+    fn end_tag(&mut self, tag: &HtmlElement) {
+        let handlers = std::mem::take(&mut self.handlers);
+
+        for handler in &handlers {
+            if handler.should_handle(&tag.tag) {
+                handler.end(tag, self);
+                break;
             }
-            _ => {}
         }
 
-        StartTagOutcome::Continue
+        self.handlers = handlers;
     }
 
-    fn end_tag(&mut self, tag: &HtmlElement) {
-        match tag.tag.as_str() {
-            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => self.push_str("\n\n"),
-            "code" => {
-                if !self.is_inside("pre") {
-                    self.push_str("`")
-                }
-            }
-            "pre" => self.push_str("\n```\n"),
-            "ul" | "ol" => self.push_newline(),
-            "li" => self.push_newline(),
-            "div" => {
-                if tag.attrs.borrow().iter().any(|attr| {
-                    attr.name.local.to_string() == "class" && attr.value.to_string() == "item-name"
-                }) {
-                    self.push_str("`: ");
-                }
-            }
-            _ => {}
+    /// Emits a buffered `TableState` as a GFM table: the header row, a
+    /// `---` separator per column, then the remaining rows. A table whose
+    /// header has no cells (including a table with no rows) emits nothing.
+    fn render_table(&mut self, table: TableState) {
+        let column_count = table
+            .header
+            .as_ref()
+            .map(|header| header.len())
+            .or_else(|| table.rows.first().map(|row| row.len()))
+            .unwrap_or(0);
+
+        if column_count == 0 {
+            return;
+        }
+
+        let header = table
+            .header
+            .unwrap_or_else(|| vec![String::new(); column_count]);
+
+        // `table` was already buffered (and bounded by `max_length`) before
+        // the table closed, so writing it out is a closer, not new visible
+        // content — force it through even if truncation happened mid-cell.
+        self.push_str_closing("\n");
+        self.push_table_row(&header);
+        self.push_str_closing(&format!("|{}\n", " --- |".repeat(column_count)));
+        for row in &table.rows {
+            self.push_table_row(row);
         }
+        self.push_str_closing("\n");
+    }
+
+    fn push_table_row(&mut self, cells: &[String]) {
+        self.push_str_closing("|");
+        for cell in cells {
+            self.push_str_closing(" ");
+            self.push_str_closing(cell);
+            self.push_str_closing(" |");
+        }
+        self.push_str_closing("\n");
+    }
+
+    fn escape_table_cell(text: &str) -> String {
+        text.replace('|', "\\|")
     }
 
     fn visit_text(&mut self, text: String) -> Result<()> {
@@ -216,8 +881,88 @@ impl MarkdownWriter {
         }
 
         let trimmed_text = text.trim_matches(|char| char == '\n' || char == '\r' || char == '§');
+        if let Some(heading_text) = self.heading_text_stack.last_mut() {
+            heading_text.push_str(trimmed_text);
+        }
         self.push_str(trimmed_text);
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use html5ever::tendril::TendrilSink;
+    use markup5ever_rcdom::RcDom;
+
+    fn parse(html: &str) -> Handle {
+        html5ever::parse_document(RcDom::default(), Default::default())
+            .from_utf8()
+            .read_from(&mut html.as_bytes())
+            .unwrap()
+            .document
+    }
+
+    fn run_truncated(html: &str, max_length: usize) -> String {
+        MarkdownWriter::new()
+            .run_truncated(&parse(html), max_length)
+            .unwrap()
+    }
+
+    #[test]
+    fn run_truncated_closes_emphasis_cut_off_mid_element() {
+        let markdown = run_truncated("<p>abc <em>defghijklmnop</em></p>", 8);
+
+        assert!(
+            markdown.contains('…'),
+            "expected a truncation marker in {markdown:?}"
+        );
+        assert!(
+            markdown.trim_end().ends_with('*'),
+            "truncating mid-<em> should still close the emphasis marker, got {markdown:?}"
+        );
+    }
+
+    #[test]
+    fn run_truncated_closes_link_cut_off_mid_element() {
+        let markdown = run_truncated(
+            r#"<p>intro <a href="https://example.com">a link whose text overruns the budget</a> trailing</p>"#,
+            10,
+        );
+
+        assert!(
+            markdown.contains('…'),
+            "expected a truncation marker in {markdown:?}"
+        );
+        assert!(
+            markdown.contains("](https://example.com)"),
+            "truncating mid-<a> should still close off the link, got {markdown:?}"
+        );
+        assert!(
+            !markdown.contains("trailing"),
+            "content after the truncation point shouldn't appear, got {markdown:?}"
+        );
+    }
+
+    #[test]
+    fn run_truncated_closes_table_cut_off_mid_cell() {
+        let markdown = run_truncated(
+            "<table><tr><td>a very long header that overruns the budget</td><td>second</td></tr></table>",
+            10,
+        );
+
+        assert!(
+            markdown.contains('…'),
+            "expected a truncation marker in {markdown:?}"
+        );
+        assert!(
+            markdown.contains("---"),
+            "truncating mid-cell should still emit a well-formed GFM table, got {markdown:?}"
+        );
+        assert!(
+            !markdown.contains("second"),
+            "content after the truncation point shouldn't appear, got {markdown:?}"
+        );
+    }
+}